@@ -1,9 +1,24 @@
 #![warn(missing_docs)]
 
 //! Signpost library for macOS and iOS
+//!
+//! On non-Apple targets, every type and macro here compiles down to an
+//! inert no-op with the same public signatures, so instrumented code can be
+//! shared across platforms without gating every call site behind its own
+//! `#[cfg(target_vendor = "apple")]`.
 
-pub use signpost_derive::{begin_interval, const_poi_logger, emit_event};
+pub use signpost_derive::{begin_interval, const_poi_logger, emit_event, log_message};
 
+#[cfg(feature = "tracing")]
+pub mod tracing_layer;
+#[cfg(feature = "tracing")]
+pub use tracing_layer::SignpostLayer;
+
+// The real signpost/os_log ABI only exists on Apple platforms. Everywhere
+// else we keep the same type aliases and function names but give them inert
+// bodies, so crates can sprinkle signpost calls through cross-platform code
+// and only pay for them (and only link against them) on Apple targets.
+#[cfg(target_vendor = "apple")]
 mod sys {
     use std::{ffi::c_void, os::raw::c_char};
 
@@ -13,11 +28,19 @@ mod sys {
     pub type os_signpost_type_t = u8;
     #[allow(non_camel_case_types)]
     pub type os_signpost_id_t = u64;
+    #[allow(non_camel_case_types)]
+    pub type os_log_type_t = u8;
 
     pub const SIGNPOST_TYPE_EVENT: os_signpost_type_t = 0;
     pub const SIGNPOST_TYPE_INTERVAL_BEGIN: os_signpost_type_t = 1;
     pub const SIGNPOST_TYPE_INTERVAL_END: os_signpost_type_t = 2;
 
+    pub const OS_LOG_TYPE_DEFAULT: os_log_type_t = 0x00;
+    pub const OS_LOG_TYPE_INFO: os_log_type_t = 0x01;
+    pub const OS_LOG_TYPE_DEBUG: os_log_type_t = 0x02;
+    pub const OS_LOG_TYPE_ERROR: os_log_type_t = 0x10;
+    pub const OS_LOG_TYPE_FAULT: os_log_type_t = 0x11;
+
     extern "C" {
         pub static mut __dso_handle: usize;
         pub static mut _os_log_default: usize;
@@ -26,6 +49,15 @@ mod sys {
 
         pub fn os_signpost_enabled(log: os_log_t) -> bool;
 
+        pub fn os_log_type_enabled(log: os_log_t, type_: os_log_type_t) -> bool;
+
+        pub fn os_signpost_id_generate(log: os_log_t) -> os_signpost_id_t;
+
+        pub fn os_signpost_id_make_with_pointer(
+            log: os_log_t,
+            ptr: *const c_void,
+        ) -> os_signpost_id_t;
+
         pub fn _os_signpost_emit_with_name_impl(
             dso: *mut c_void,
             log: os_log_t,
@@ -36,11 +68,96 @@ mod sys {
             buf: *mut u8,
             size: u32,
         );
+
+        pub fn _os_log_impl(
+            dso: *mut c_void,
+            log: os_log_t,
+            type_: os_log_type_t,
+            format: *const c_char,
+            buf: *mut u8,
+            size: u32,
+        );
+    }
+}
+
+/// Stand-in for the `sys` module above on targets that don't have the
+/// `os_log`/`os_signpost` ABI. Every function is a safe no-op; callers
+/// further up still wrap them in `unsafe` blocks, matching the real module.
+#[cfg(not(target_vendor = "apple"))]
+mod sys {
+    use std::{ffi::c_void, os::raw::c_char};
+
+    #[allow(non_camel_case_types)]
+    pub type os_log_t = usize;
+    #[allow(non_camel_case_types)]
+    pub type os_signpost_type_t = u8;
+    #[allow(non_camel_case_types)]
+    pub type os_signpost_id_t = u64;
+    #[allow(non_camel_case_types)]
+    pub type os_log_type_t = u8;
+
+    pub const SIGNPOST_TYPE_EVENT: os_signpost_type_t = 0;
+    pub const SIGNPOST_TYPE_INTERVAL_BEGIN: os_signpost_type_t = 1;
+    pub const SIGNPOST_TYPE_INTERVAL_END: os_signpost_type_t = 2;
+
+    pub const OS_LOG_TYPE_DEFAULT: os_log_type_t = 0x00;
+    pub const OS_LOG_TYPE_INFO: os_log_type_t = 0x01;
+    pub const OS_LOG_TYPE_DEBUG: os_log_type_t = 0x02;
+    pub const OS_LOG_TYPE_ERROR: os_log_type_t = 0x10;
+    pub const OS_LOG_TYPE_FAULT: os_log_type_t = 0x11;
+
+    #[allow(non_upper_case_globals)]
+    pub static mut __dso_handle: usize = 0;
+
+    pub unsafe fn os_log_create(_subsystem: *const c_char, _category: *const c_char) -> os_log_t {
+        0
+    }
+
+    pub unsafe fn os_signpost_enabled(_log: os_log_t) -> bool {
+        false
+    }
+
+    pub unsafe fn os_log_type_enabled(_log: os_log_t, _type_: os_log_type_t) -> bool {
+        false
+    }
+
+    pub unsafe fn os_signpost_id_generate(_log: os_log_t) -> os_signpost_id_t {
+        1
+    }
+
+    pub unsafe fn os_signpost_id_make_with_pointer(
+        _log: os_log_t,
+        _ptr: *const c_void,
+    ) -> os_signpost_id_t {
+        1
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub unsafe fn _os_signpost_emit_with_name_impl(
+        _dso: *mut c_void,
+        _log: os_log_t,
+        _type_: os_signpost_type_t,
+        _spid: os_signpost_id_t,
+        _name: *const c_char,
+        _format: *const u8,
+        _buf: *mut u8,
+        _size: u32,
+    ) {
+    }
+
+    pub unsafe fn _os_log_impl(
+        _dso: *mut c_void,
+        _log: os_log_t,
+        _type_: os_log_type_t,
+        _format: *const c_char,
+        _buf: *mut u8,
+        _size: u32,
+    ) {
     }
 }
 
 use std::{
-    ffi::CStr,
+    ffi::{c_void, CStr},
     sync::{
         atomic::{AtomicUsize, Ordering},
         Once,
@@ -62,6 +179,201 @@ pub struct SignpostInterval<'a> {
     name: &'a CStr,
 }
 
+/// Identifier disambiguating overlapping signpost events and intervals
+///
+/// Must not be one of the built-in sentinel values: zero or `u64::MAX`.
+/// Rather than inventing one by hand, prefer [OsLog::generate_id] or
+/// [OsLog::id_for_ptr], which can never produce a sentinel value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SignpostId(u64);
+
+impl SignpostId {
+    /// Build a signpost ID from a raw value
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` is one of the reserved sentinel values: zero or
+    /// `u64::MAX`.
+    pub fn new(id: u64) -> Self {
+        assert!(
+            id != 0 && id != u64::MAX,
+            "signpost id must not be 0 or u64::MAX"
+        );
+        SignpostId(id)
+    }
+
+    fn raw(self) -> u64 {
+        self.0
+    }
+}
+
+impl From<u64> for SignpostId {
+    fn from(id: u64) -> Self {
+        SignpostId::new(id)
+    }
+}
+
+/// Severity level for a unified-logging message written with [OsLog::log]
+///
+/// See <https://developer.apple.com/documentation/os/oslogtype>
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogLevel {
+    /// Useful only during debugging, and not persisted to disk
+    Debug,
+    /// Helpful but not essential for troubleshooting, captured in memory
+    /// and persisted to disk only for a short time
+    Info,
+    /// Essential for troubleshooting, always captured and persisted to disk
+    Default,
+    /// A process-level error, always captured and persisted to disk
+    Error,
+    /// A multi-process or system-level fault, always captured and persisted
+    /// to disk
+    Fault,
+}
+
+impl LogLevel {
+    fn os_log_type(self) -> sys::os_log_type_t {
+        match self {
+            LogLevel::Debug => sys::OS_LOG_TYPE_DEBUG,
+            LogLevel::Info => sys::OS_LOG_TYPE_INFO,
+            LogLevel::Default => sys::OS_LOG_TYPE_DEFAULT,
+            LogLevel::Error => sys::OS_LOG_TYPE_ERROR,
+            LogLevel::Fault => sys::OS_LOG_TYPE_FAULT,
+        }
+    }
+}
+
+/// A value that can be attached as a typed argument to a signpost event or
+/// interval.
+///
+/// This is implemented for the common integer and floating point
+/// primitives, and for `&CStr` (encoded as a C string pointer, for use with
+/// the `%{public}s`/`%s` format specifiers). You should not need to
+/// implement this yourself: the [emit_event] and [begin_interval] macros
+/// pick the right implementation based on the arguments you pass.
+pub trait SignpostArgument {
+    /// The low nibble of the `os_log` argument descriptor: `0` for scalar
+    /// (integer/float) values, `2` for a C string pointer.
+    #[doc(hidden)]
+    const ITEM_TYPE: u8;
+
+    /// Upper bound on the number of bytes [SignpostArgument::write_payload]
+    /// can write, used by [__private::ArgBuffer] to reject a push that
+    /// wouldn't fit, instead of indexing past the end of the buffer.
+    #[doc(hidden)]
+    const MAX_PAYLOAD_LEN: usize;
+
+    /// Write the little-endian payload bytes for this argument into `out`,
+    /// returning the number of bytes written.
+    #[doc(hidden)]
+    fn write_payload(&self, out: &mut [u8]) -> u8;
+}
+
+macro_rules! impl_signpost_argument_scalar {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl SignpostArgument for $ty {
+                const ITEM_TYPE: u8 = 0;
+                const MAX_PAYLOAD_LEN: usize = std::mem::size_of::<$ty>();
+
+                fn write_payload(&self, out: &mut [u8]) -> u8 {
+                    let bytes = self.to_le_bytes();
+                    out[..bytes.len()].copy_from_slice(&bytes);
+                    bytes.len() as u8
+                }
+            }
+        )*
+    };
+}
+
+impl_signpost_argument_scalar!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize, f32, f64);
+
+impl SignpostArgument for &CStr {
+    const ITEM_TYPE: u8 = 2;
+    const MAX_PAYLOAD_LEN: usize = std::mem::size_of::<u64>();
+
+    fn write_payload(&self, out: &mut [u8]) -> u8 {
+        let bytes = (self.as_ptr() as u64).to_le_bytes();
+        out[..bytes.len()].copy_from_slice(&bytes);
+        bytes.len() as u8
+    }
+}
+
+/// Implementation details used by the code generated from the
+/// [emit_event]/[begin_interval] macros.
+///
+/// The layout encoded here matches what `_os_signpost_emit_with_name_impl`
+/// expects in its `buf` argument: a summary flags byte, an argument count
+/// byte, and then one descriptor/length/payload triple per argument.
+#[doc(hidden)]
+pub mod __private {
+    use super::SignpostArgument;
+
+    const PRIVATE: u8 = 0x01;
+    const PUBLIC: u8 = 0x02;
+    const HAS_PRIVATE: u8 = 0x01;
+    const HAS_NON_SCALAR: u8 = 0x02;
+
+    pub struct ArgBuffer<'a> {
+        buf: &'a mut [u8],
+        offset: usize,
+        count: u8,
+        summary: u8,
+        overflowed: bool,
+    }
+
+    impl<'a> ArgBuffer<'a> {
+        pub fn new(buf: &'a mut [u8]) -> Self {
+            ArgBuffer {
+                buf,
+                offset: 2,
+                count: 0,
+                summary: 0,
+                overflowed: false,
+            }
+        }
+
+        /// Encode `value` as the next argument.
+        ///
+        /// The generated code sizes `buf` to fit every argument it pushes,
+        /// so this should never actually overflow; but `buf` can also be
+        /// built by hand (it's only `#[doc(hidden)]`, not private), so this
+        /// still checks before writing. A push that wouldn't fit is dropped
+        /// -- leaving `buf` one argument short rather than indexing past its
+        /// end -- and every push after the first overflow is dropped too,
+        /// since the descriptor for a dropped argument would otherwise
+        /// silently reuse the bytes of the one before it.
+        pub fn push<T: SignpostArgument>(&mut self, public: bool, value: &T) {
+            if self.overflowed || self.offset + 2 + T::MAX_PAYLOAD_LEN > self.buf.len() {
+                self.overflowed = true;
+                return;
+            }
+
+            let visibility = if public { PUBLIC } else { PRIVATE };
+            let descriptor = (visibility << 4) | T::ITEM_TYPE;
+
+            self.buf[self.offset] = descriptor;
+            let len = value.write_payload(&mut self.buf[self.offset + 2..]);
+            self.buf[self.offset + 1] = len;
+            self.offset += 2 + len as usize;
+            self.count += 1;
+
+            if !public {
+                self.summary |= HAS_PRIVATE;
+            }
+            if T::ITEM_TYPE != 0 {
+                self.summary |= HAS_NON_SCALAR;
+            }
+        }
+
+        pub fn finish(self) {
+            self.buf[0] = self.summary;
+            self.buf[1] = self.count;
+        }
+    }
+}
+
 impl OsLog {
     /// Create a new signpost logger
     ///
@@ -112,29 +424,120 @@ impl OsLog {
         self
     }
 
+    /// Generate a signpost ID unique to this logger
+    ///
+    /// See <https://developer.apple.com/documentation/os/3019999-os_signpost_id_generate>
+    pub fn generate_id(&self) -> SignpostId {
+        let log = self.get();
+        SignpostId(unsafe { sys::os_signpost_id_generate(log) })
+    }
+
+    /// Derive a signpost ID from a pointer
+    ///
+    /// This ties a begin/end pair to a specific object instance (e.g. a
+    /// request or connection), so overlapping intervals of the same kind
+    /// are disambiguated automatically.
+    ///
+    /// See <https://developer.apple.com/documentation/os/3019998-os_signpost_id_make_with_pointer>
+    pub fn id_for_ptr<T>(&self, ptr: &T) -> SignpostId {
+        let log = self.get();
+        SignpostId(unsafe {
+            sys::os_signpost_id_make_with_pointer(log, ptr as *const T as *const c_void)
+        })
+    }
+
     /// Emit an event to the logger
-    /// 
+    ///
     /// Use this to add a single point in time to the "Points of Interest"
     /// in Instruments.
-    /// 
+    ///
     /// The ID is arbitrary but must *not* be one of the built-in sentinel
-    /// values: zero or u64::MAX.
-    /// 
+    /// values: zero or u64::MAX. Prefer [OsLog::generate_id] or
+    /// [OsLog::id_for_ptr] over inventing one by hand.
+    ///
     /// Avoid creating event names at runtime, prefer using the
     /// [emit_event] macro instead.
-    pub fn emit_event(&self, id: u64, name: &CStr) {
-        let log = self.get();
+    pub fn emit_event(&self, id: impl Into<SignpostId>, name: &CStr) {
         let mut buf = [0u8; 64];
+        self.emit_raw(
+            sys::SIGNPOST_TYPE_EVENT,
+            id.into().raw(),
+            name,
+            std::ptr::null(),
+            &mut buf,
+        );
+    }
+
+    /// Emit an event with a printf-style format string and typed arguments
+    ///
+    /// This is the building block behind the variadic form of
+    /// [emit_event]; prefer using the macro directly instead of calling
+    /// this yourself.
+    #[doc(hidden)]
+    pub fn emit_event_with_args(
+        &self,
+        id: impl Into<SignpostId>,
+        name: &CStr,
+        format: &'static CStr,
+        buf: &mut [u8],
+    ) {
+        self.emit_raw(
+            sys::SIGNPOST_TYPE_EVENT,
+            id.into().raw(),
+            name,
+            format.as_ptr() as *const u8,
+            buf,
+        );
+    }
+
+    /// Write a message to the unified logging system at the given severity
+    ///
+    /// Unlike [OsLog::emit_event] and [OsLog::begin_interval], this does not
+    /// show up as a signpost in Instruments; instead it surfaces in Console
+    /// and `log stream`, the same as the standard `os_log` C macro.
+    ///
+    /// `message` is treated as plain text, not a format string: it's passed
+    /// to `os_log` as a `%{public}s` argument rather than as the `format`
+    /// itself, so a `%` anywhere in a runtime-built message can't be
+    /// mistaken for a conversion specifier.
+    ///
+    /// Avoid creating log messages at runtime, prefer using the
+    /// [log_message] macro instead.
+    pub fn log(&self, level: LogLevel, message: &CStr) {
+        const MESSAGE_FORMAT: &CStr =
+            unsafe { &*(b"%{public}s\0" as *const [u8] as *const CStr) };
+
+        let mut buf = [0u8; 12];
+        {
+            let mut args = __private::ArgBuffer::new(&mut buf);
+            args.push(true, &message);
+            args.finish();
+        }
+        self.log_raw(level, MESSAGE_FORMAT.as_ptr() as *const u8, &mut buf);
+    }
+
+    /// Write a message with a printf-style format string and typed
+    /// arguments
+    ///
+    /// This is the building block behind the variadic form of
+    /// [log_message]; prefer using the macro directly instead of calling
+    /// this yourself.
+    #[doc(hidden)]
+    pub fn log_with_args(&self, level: LogLevel, format: &'static CStr, buf: &mut [u8]) {
+        self.log_raw(level, format.as_ptr() as *const u8, buf);
+    }
+
+    fn log_raw(&self, level: LogLevel, format: *const u8, buf: &mut [u8]) {
+        let log = self.get();
+        let type_ = level.os_log_type();
 
         unsafe {
-            if sys::os_signpost_enabled(log) {
-                sys::_os_signpost_emit_with_name_impl(
+            if sys::os_log_type_enabled(log, type_) {
+                sys::_os_log_impl(
                     (&mut sys::__dso_handle) as *mut usize as *mut _,
                     log,
-                    sys::SIGNPOST_TYPE_EVENT,
-                    id,
-                    name.as_ptr(),
-                    std::ptr::null(),
+                    type_,
+                    format as *const _,
                     buf.as_mut_ptr(),
                     buf.len() as u32,
                 )
@@ -143,36 +546,90 @@ impl OsLog {
     }
 
     /// Start a timed event
-    /// 
+    ///
     /// The ID is used to disambiguate overlapping events, so make sure that
-    /// it's unique among events that can overlap in time.
-    /// 
-    /// Avoid create interval names at runtime, prefer using the 
+    /// it's unique among events that can overlap in time. Prefer
+    /// [OsLog::generate_id] or [OsLog::id_for_ptr] over inventing one by
+    /// hand.
+    ///
+    /// Avoid create interval names at runtime, prefer using the
     /// [begin_interval] macro instead.
-    pub fn begin_interval<'a>(&'a self, id: u64, name: &'a CStr) -> SignpostInterval<'a> {
-        let log_handle = self.get();
+    pub fn begin_interval<'a>(
+        &'a self,
+        id: impl Into<SignpostId>,
+        name: &'a CStr,
+    ) -> SignpostInterval<'a> {
+        let id = id.into().raw();
         let mut buf = [0u8; 64];
+        self.emit_raw(
+            sys::SIGNPOST_TYPE_INTERVAL_BEGIN,
+            id,
+            name,
+            std::ptr::null(),
+            &mut buf,
+        );
+
+        SignpostInterval {
+            log: self,
+            id,
+            name,
+        }
+    }
+
+    /// Start a timed event with a printf-style format string and typed
+    /// arguments
+    ///
+    /// This is the building block behind the variadic form of
+    /// [begin_interval]; prefer using the macro directly instead of calling
+    /// this yourself.
+    #[doc(hidden)]
+    pub fn begin_interval_with_args<'a>(
+        &'a self,
+        id: impl Into<SignpostId>,
+        name: &'a CStr,
+        format: &'static CStr,
+        buf: &mut [u8],
+    ) -> SignpostInterval<'a> {
+        let id = id.into().raw();
+        self.emit_raw(
+            sys::SIGNPOST_TYPE_INTERVAL_BEGIN,
+            id,
+            name,
+            format.as_ptr() as *const u8,
+            buf,
+        );
+
+        SignpostInterval {
+            log: self,
+            id,
+            name,
+        }
+    }
+
+    fn emit_raw(
+        &self,
+        type_: sys::os_signpost_type_t,
+        id: u64,
+        name: &CStr,
+        format: *const u8,
+        buf: &mut [u8],
+    ) {
+        let log = self.get();
 
         unsafe {
-            if sys::os_signpost_enabled(log_handle) {
+            if sys::os_signpost_enabled(log) {
                 sys::_os_signpost_emit_with_name_impl(
                     (&mut sys::__dso_handle) as *mut usize as *mut _,
-                    log_handle,
-                    sys::SIGNPOST_TYPE_INTERVAL_BEGIN,
+                    log,
+                    type_,
                     id,
                     name.as_ptr(),
-                    std::ptr::null(),
+                    format,
                     buf.as_mut_ptr(),
                     buf.len() as u32,
                 )
             }
         }
-
-        SignpostInterval {
-            log: self,
-            id,
-            name,
-        }
     }
 
     fn get(&self) -> sys::os_log_t {
@@ -192,21 +649,12 @@ impl OsLog {
 impl<'a> Drop for SignpostInterval<'a> {
     fn drop(&mut self) {
         let mut buf = [0u8; 4];
-        let log_handle = self.log.get();
-
-        unsafe {
-            if sys::os_signpost_enabled(log_handle) {
-                sys::_os_signpost_emit_with_name_impl(
-                    (&mut sys::__dso_handle) as *mut usize as *mut _,
-                    log_handle,
-                    sys::SIGNPOST_TYPE_INTERVAL_END,
-                    self.id,
-                    self.name.as_ptr(),
-                    std::ptr::null(),
-                    buf.as_mut_ptr(),
-                    buf.len() as u32,
-                )
-            }
-        }
+        self.log.emit_raw(
+            sys::SIGNPOST_TYPE_INTERVAL_END,
+            self.id,
+            self.name,
+            std::ptr::null(),
+            &mut buf,
+        );
     }
 }
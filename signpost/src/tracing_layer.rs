@@ -0,0 +1,128 @@
+//! `tracing_subscriber::Layer` that mirrors `tracing` spans/events onto signpost
+
+use std::ffi::{CStr, CString};
+
+use tracing::span::Id;
+use tracing::{Event, Subscriber};
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+
+use crate::{sys, OsLog};
+
+/// A [Layer] that turns `tracing` spans into signpost intervals and
+/// `tracing` events into signpost events.
+///
+/// Add it to a `tracing_subscriber::Registry` to see existing
+/// `#[tracing::instrument]`ed code light up in Instruments with zero manual
+/// `begin_interval!`/`emit_event!` calls:
+///
+/// ```ignore
+/// use tracing_subscriber::prelude::*;
+///
+/// tracing_subscriber::registry()
+///     .with(signpost::tracing_layer::SignpostLayer::new(LOGGER_SUBSYSTEM))
+///     .init();
+/// ```
+///
+/// The signpost name is derived from the span's or event's
+/// `tracing::Metadata::name()`. On targets without the Apple signpost ABI
+/// this still compiles (thanks to [OsLog]'s no-op fallback) but does
+/// nothing, so it's safe to leave in cross-platform code.
+pub struct SignpostLayer {
+    log: OsLog,
+}
+
+impl SignpostLayer {
+    /// Build a layer that logs to a fresh [OsLog] with the given subsystem
+    /// name and the "Points of Interest" category.
+    pub fn new(subsystem: &'static CStr) -> Self {
+        SignpostLayer::with_log(OsLog::new(subsystem, OsLog::CATEGORY_POINTS_OF_INTEREST))
+    }
+
+    /// Build a layer on top of an already constructed [OsLog], e.g. one
+    /// using a non-default category via [OsLog::with_category].
+    pub fn with_log(log: OsLog) -> Self {
+        SignpostLayer { log }
+    }
+}
+
+/// The open interval for a span: the id it was begun with, plus the boxed
+/// name it was begun with (`emit_raw` only borrows `name` for the duration
+/// of the begin/end calls, so there's no reference here that needs to
+/// outlive this struct).
+struct SpanInterval {
+    id: u64,
+    name: Box<CStr>,
+}
+
+impl<S> Layer<S> for SignpostLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_enter(&self, id: &Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        if span.extensions().get::<SpanInterval>().is_some() {
+            // Spans can be entered more than once (e.g. across `.await`
+            // points); only the first entry starts the interval.
+            return;
+        }
+        let Some(metadata) = ctx.metadata(id) else {
+            return;
+        };
+        let Ok(name) = CString::new(metadata.name()) else {
+            return;
+        };
+        let name = name.into_boxed_c_str();
+        let raw_id = id.clone().into_u64();
+
+        let mut buf = [0u8; 4];
+        self.log.emit_raw(
+            sys::SIGNPOST_TYPE_INTERVAL_BEGIN,
+            raw_id,
+            &name,
+            std::ptr::null(),
+            &mut buf,
+        );
+
+        span.extensions_mut().insert(SpanInterval { id: raw_id, name });
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(&id) else {
+            return;
+        };
+        let Some(interval) = span.extensions_mut().remove::<SpanInterval>() else {
+            return;
+        };
+
+        let mut buf = [0u8; 4];
+        self.log.emit_raw(
+            sys::SIGNPOST_TYPE_INTERVAL_END,
+            interval.id,
+            &interval.name,
+            std::ptr::null(),
+            &mut buf,
+        );
+    }
+
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let Ok(name) = CString::new(event.metadata().name()) else {
+            return;
+        };
+        self.log.emit_event(event_id(event), &name);
+    }
+}
+
+/// Events have no identity of their own, so fold their static metadata's
+/// address into a non-sentinel id. Events from the same call site sharing
+/// an id is fine: unlike intervals, events don't need to be disambiguated
+/// from each other.
+fn event_id(event: &Event<'_>) -> u64 {
+    match event.metadata() as *const _ as u64 {
+        0 | u64::MAX => 1,
+        ptr => ptr,
+    }
+}
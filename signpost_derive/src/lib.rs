@@ -3,7 +3,7 @@
 //! Compile-time convenience macros for the `signpost` crate
 
 use proc_macro::TokenStream;
-use quote::{quote, ToTokens};
+use quote::{format_ident, quote, ToTokens};
 use syn::{
     parse::{Parse, ParseStream},
     parse_macro_input, Expr, LitByteStr, LitStr, Result, Token,
@@ -44,7 +44,10 @@ pub fn const_poi_logger(input: TokenStream) -> TokenStream {
 /// The arguments are `logger`, `id`, `name`:
 ///
 /// * `id` needs to be a non-zero positive integer, preferably unique
-///   per type of event logged
+///   per type of event logged, or a
+///   [signpost::SignpostId](../signpost/struct.SignpostId.html) from
+///   [OsLog::generate_id](../signpost/struct.OsLog.html#method.generate_id)
+///   or `id_for_ptr`.
 /// * `name` is a string literal that will identify the event in Instruments.
 ///
 /// ```ignore
@@ -55,23 +58,22 @@ pub fn const_poi_logger(input: TokenStream) -> TokenStream {
 ///     signpost::emit_event!(LOGGER, 1, "My event");
 /// }
 /// ```
+///
+/// A printf-style format string and typed arguments can be appended after
+/// `name` to attach structured metadata that shows up in the Instruments
+/// detail pane:
+///
+/// ```ignore
+/// signpost::emit_event!(LOGGER, 1, "Load", "rows=%ld file=%{public}s", n_rows, path);
+/// ```
+///
+/// Each `%`-specifier in the format string consumes one argument, in order.
+/// Integer and floating point arguments are passed by value; `%s`/`%{public}s`
+/// arguments must be a `&CStr`. Arguments are private by default; mark a
+/// specifier `%{public}...` to make it public.
 #[proc_macro]
 pub fn emit_event(input: TokenStream) -> TokenStream {
-    let EventArgs { log, id, name } = parse_macro_input!(input as EventArgs);
-
-    let name_bstr = match str_lit_to_static_cstr(name) {
-        Ok(name_bstr) => name_bstr,
-        Err(name) => {
-            return TokenStream::from(
-                syn::Error::new_spanned(name, "The event name can not contain NULL bytes")
-                    .into_compile_error(),
-            )
-        }
-    };
-
-    let call = quote! { #log.emit_event(#id, #name_bstr) };
-
-    TokenStream::from(call)
+    expand_signpost_macro(input, "emit_event", "emit_event_with_args")
 }
 
 /// Start a signpost interval on a logger
@@ -89,9 +91,101 @@ pub fn emit_event(input: TokenStream) -> TokenStream {
 ///     // `_interval` will end the interval when it is dropped
 /// }
 /// ```
+///
+/// Like [emit_event], a format string and typed arguments can be appended
+/// after `name`:
+///
+/// ```ignore
+/// let _interval = signpost::begin_interval!(LOGGER, 2, "Compute result", "rows=%ld", n_rows);
+/// ```
 #[proc_macro]
 pub fn begin_interval(input: TokenStream) -> TokenStream {
-    let EventArgs { log, id, name } = parse_macro_input!(input as EventArgs);
+    expand_signpost_macro(input, "begin_interval", "begin_interval_with_args")
+}
+
+/// Write a message to the unified logging system.
+///
+/// The arguments are `logger`, `level`, `format string`, `format args...`:
+///
+/// * `level` is a [signpost::LogLevel](../signpost/enum.LogLevel.html) value.
+/// * `format` is a printf-style format string, just like [emit_event]'s.
+///
+/// ```ignore
+/// use signpost::{LogLevel, OsLog, const_poi_logger};
+/// static LOGGER: OsLog = const_poi_logger!("Subsystem name")
+///
+/// fn myfunc() {
+///     signpost::log_message!(LOGGER, LogLevel::Error, "Failed to load %ld rows", n_rows);
+/// }
+/// ```
+#[proc_macro]
+pub fn log_message(input: TokenStream) -> TokenStream {
+    let LogArgs {
+        log,
+        level,
+        format,
+        args,
+    } = parse_macro_input!(input as LogArgs);
+
+    let specs = match parse_format_specs(&format.value()) {
+        Ok(specs) => specs,
+        Err(message) => {
+            return TokenStream::from(
+                syn::Error::new_spanned(&format, message).into_compile_error(),
+            )
+        }
+    };
+
+    if specs.len() != args.len() {
+        return TokenStream::from(
+            syn::Error::new_spanned(
+                &format,
+                format!(
+                    "format string has {} argument specifier(s), but {} argument(s) were passed",
+                    specs.len(),
+                    args.len()
+                ),
+            )
+            .into_compile_error(),
+        );
+    }
+
+    if args.is_empty() {
+        let format_bstr = match str_lit_to_static_cstr(format.clone()) {
+            Ok(format_bstr) => format_bstr,
+            Err(format) => {
+                return TokenStream::from(
+                    syn::Error::new_spanned(
+                        format,
+                        "The format string can not contain NULL bytes",
+                    )
+                    .into_compile_error(),
+                )
+            }
+        };
+        return TokenStream::from(quote! { #log.log(#level, #format_bstr) });
+    }
+
+    let call = match expand_formatted_call(&log, "log_with_args", &format, &args, &[quote! { #level }]) {
+        Ok(call) => call,
+        Err(err) => err,
+    };
+
+    TokenStream::from(call)
+}
+
+fn expand_signpost_macro(
+    input: TokenStream,
+    simple_method: &str,
+    formatted_method: &str,
+) -> TokenStream {
+    let EventArgs {
+        log,
+        id,
+        name,
+        format,
+        args,
+    } = parse_macro_input!(input as EventArgs);
 
     let name_bstr = match str_lit_to_static_cstr(name) {
         Ok(name_bstr) => name_bstr,
@@ -103,15 +197,95 @@ pub fn begin_interval(input: TokenStream) -> TokenStream {
         }
     };
 
-    let call = quote! { #log.begin_interval(#id, #name_bstr) };
+    let format = match format {
+        None => {
+            let simple_method = format_ident!("{}", simple_method);
+            return TokenStream::from(quote! { #log.#simple_method(#id, #name_bstr) });
+        }
+        Some(format) => format,
+    };
+
+    let call = match expand_formatted_call(
+        &log,
+        formatted_method,
+        &format,
+        &args,
+        &[quote! { #id }, quote! { #name_bstr }],
+    ) {
+        Ok(call) => call,
+        Err(err) => err,
+    };
 
     TokenStream::from(call)
 }
 
+/// Shared codegen for every macro's "has a format string" case: validate the
+/// format string against the arguments, then build the `ArgBuffer`-filling
+/// block and the trailing call to `method`, with `leading_args` (e.g. `id`
+/// and `name` for [emit_event]/[begin_interval]) spliced in before the
+/// format string.
+///
+/// Returns the generated call on success, or a compile error `TokenStream`
+/// ready to return from the calling proc macro.
+fn expand_formatted_call(
+    log: &Expr,
+    method: &str,
+    format: &LitStr,
+    args: &[Expr],
+    leading_args: &[proc_macro2::TokenStream],
+) -> std::result::Result<proc_macro2::TokenStream, proc_macro2::TokenStream> {
+    let specs = parse_format_specs(&format.value())
+        .map_err(|message| syn::Error::new_spanned(format, message).into_compile_error())?;
+
+    if specs.len() != args.len() {
+        return Err(syn::Error::new_spanned(
+            format,
+            format!(
+                "format string has {} argument specifier(s), but {} argument(s) were passed",
+                specs.len(),
+                args.len()
+            ),
+        )
+        .into_compile_error());
+    }
+
+    let format_bstr = str_lit_to_static_cstr(format.clone())
+        .map_err(|format| {
+            syn::Error::new_spanned(format, "The format string can not contain NULL bytes")
+                .into_compile_error()
+        })?
+        .into_token_stream();
+
+    let pushes = specs.iter().zip(args.iter()).map(|(spec, arg)| {
+        let public = spec.public;
+        quote! { __signpost_args.push(#public, &(#arg)); }
+    });
+
+    // 2 header bytes, plus a 2-byte descriptor/length and up to 8 bytes of
+    // payload per argument (the widest `SignpostArgument` impls are the
+    // 64-bit integer/float types and `&CStr`'s pointer).
+    let buf_size = 2 + args.len() * 10;
+    let method = format_ident!("{}", method);
+
+    Ok(quote! {
+        {
+            let mut __signpost_buf = [0u8; #buf_size];
+            {
+                let mut __signpost_args = signpost::__private::ArgBuffer::new(&mut __signpost_buf);
+                #(#pushes)*
+                __signpost_args.finish();
+            }
+            #log.#method(#(#leading_args,)* #format_bstr, &mut __signpost_buf)
+        }
+    })
+}
+
 struct EventArgs {
     log: Expr,
     id: Expr,
     name: LitStr,
+    format: Option<LitStr>,
+    args: Vec<Expr>,
 }
 
 impl Parse for EventArgs {
@@ -121,9 +295,64 @@ impl Parse for EventArgs {
         let id = input.parse::<Expr>()?;
         input.parse::<Token![,]>()?;
         let name = input.parse::<LitStr>()?;
-        input.parse::<Option<Token![,]>>()?;
 
-        Ok(EventArgs { log, id, name })
+        let mut format = None;
+        let mut args = Vec::new();
+
+        if !input.is_empty() {
+            input.parse::<Token![,]>()?;
+            if !input.is_empty() {
+                format = Some(input.parse::<LitStr>()?);
+                while !input.is_empty() {
+                    input.parse::<Token![,]>()?;
+                    if input.is_empty() {
+                        break;
+                    }
+                    args.push(input.parse::<Expr>()?);
+                }
+            }
+        }
+
+        Ok(EventArgs {
+            log,
+            id,
+            name,
+            format,
+            args,
+        })
+    }
+}
+
+struct LogArgs {
+    log: Expr,
+    level: Expr,
+    format: LitStr,
+    args: Vec<Expr>,
+}
+
+impl Parse for LogArgs {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let log = input.parse::<Expr>()?;
+        input.parse::<Token![,]>()?;
+        let level = input.parse::<Expr>()?;
+        input.parse::<Token![,]>()?;
+        let format = input.parse::<LitStr>()?;
+
+        let mut args = Vec::new();
+        while !input.is_empty() {
+            input.parse::<Token![,]>()?;
+            if input.is_empty() {
+                break;
+            }
+            args.push(input.parse::<Expr>()?);
+        }
+
+        Ok(LogArgs {
+            log,
+            level,
+            format,
+            args,
+        })
     }
 }
 
@@ -140,6 +369,74 @@ impl Parse for PoiLoggerArgs {
     }
 }
 
+/// One `%`-specifier found in a signpost format string.
+struct ArgSpec {
+    public: bool,
+}
+
+/// Parse the `%`-specifiers out of a printf-style format string, in order.
+///
+/// This only needs to recognize enough of the syntax to recover each
+/// specifier's visibility (`%{public}...` vs. the private default) and to
+/// reject unsupported conversions; the actual formatting happens in
+/// Instruments/`log`, not here.
+fn parse_format_specs(format: &str) -> std::result::Result<Vec<ArgSpec>, String> {
+    let mut specs = Vec::new();
+    let mut chars = format.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            continue;
+        }
+        if chars.peek() == Some(&'%') {
+            chars.next();
+            continue;
+        }
+
+        let mut public = false;
+        if chars.peek() == Some(&'{') {
+            chars.next();
+            let mut tag = String::new();
+            for ch in chars.by_ref() {
+                if ch == '}' {
+                    break;
+                }
+                tag.push(ch);
+            }
+            public = tag.split(',').any(|t| t.trim() == "public");
+        }
+
+        while matches!(chars.peek(), Some('-') | Some('+') | Some(' ') | Some('0') | Some('#')) {
+            chars.next();
+        }
+        while matches!(chars.peek(), Some(c) if c.is_ascii_digit()) {
+            chars.next();
+        }
+        if chars.peek() == Some(&'.') {
+            chars.next();
+            while matches!(chars.peek(), Some(c) if c.is_ascii_digit()) {
+                chars.next();
+            }
+        }
+        while matches!(
+            chars.peek(),
+            Some('l') | Some('h') | Some('z') | Some('j') | Some('t') | Some('q')
+        ) {
+            chars.next();
+        }
+
+        match chars.next() {
+            Some('s') | Some('@') | Some('d') | Some('i') | Some('u') | Some('x') | Some('X')
+            | Some('o') | Some('f') | Some('e') | Some('E') | Some('g') | Some('G')
+            | Some('c') | Some('p') => specs.push(ArgSpec { public }),
+            Some(other) => return Err(format!("unsupported format specifier '%{}'", other)),
+            None => return Err("truncated format specifier in format string".to_string()),
+        }
+    }
+
+    Ok(specs)
+}
+
 fn str_lit_to_static_cstr(input: LitStr) -> std::result::Result<impl ToTokens, LitStr> {
     let mut name_str = input.value();
     if name_str.contains('\0') {
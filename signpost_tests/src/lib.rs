@@ -13,3 +13,75 @@ fn emit_interval() {
 
     std::thread::sleep(std::time::Duration::from_millis(10));
 }
+
+#[test]
+fn emit_event_with_formatted_args() {
+    use std::ffi::CString;
+
+    static LOGGER: signpost::OsLog = signpost::const_poi_logger!("mhallin.github.io");
+    let path = CString::new("/tmp/data.bin").unwrap();
+
+    signpost::emit_event!(
+        LOGGER,
+        12,
+        "Load",
+        "rows=%ld file=%{public}s",
+        42i64,
+        path.as_c_str()
+    );
+}
+
+#[test]
+fn log_message_macro() {
+    static LOGGER: signpost::OsLog = signpost::const_poi_logger!("mhallin.github.io");
+
+    signpost::log_message!(LOGGER, signpost::LogLevel::Default, "Starting up");
+    signpost::log_message!(
+        LOGGER,
+        signpost::LogLevel::Error,
+        "Failed to load %ld rows",
+        13i64
+    );
+}
+
+#[test]
+fn log_runtime_message() {
+    use std::ffi::CString;
+
+    static LOGGER: signpost::OsLog = signpost::const_poi_logger!("mhallin.github.io");
+
+    // A message built at runtime, containing a literal '%', must not be
+    // misinterpreted as a format specifier.
+    let message = CString::new(format!("{}% done", 100)).unwrap();
+    LOGGER.log(signpost::LogLevel::Default, &message);
+}
+
+#[test]
+fn tracing_layer_spans_and_events() {
+    use signpost::tracing_layer::SignpostLayer;
+    use tracing_subscriber::prelude::*;
+
+    let subsystem: &'static std::ffi::CStr =
+        unsafe { &*(b"mhallin.github.io\0" as *const [u8] as *const std::ffi::CStr) };
+    let subscriber = tracing_subscriber::registry().with(SignpostLayer::new(subsystem));
+
+    tracing::subscriber::with_default(subscriber, || {
+        let span = tracing::info_span!("traced work");
+        let _entered = span.enter();
+        tracing::info!("inside span");
+        drop(_entered);
+        drop(span);
+    });
+}
+
+#[test]
+fn signpost_ids() {
+    static LOGGER: signpost::OsLog = signpost::const_poi_logger!("mhallin.github.io");
+
+    let request = 42u32;
+    let generated = LOGGER.generate_id();
+    let for_ptr = LOGGER.id_for_ptr(&request);
+
+    signpost::emit_event!(LOGGER, generated, "Generated id");
+    signpost::emit_event!(LOGGER, for_ptr, "Pointer-derived id");
+}